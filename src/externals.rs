@@ -0,0 +1,621 @@
+#[cfg(target_arch = "wasm32")]
+use super::unsafe_alloc_buffer;
+use super::{Address, Difficulty, EtherValue, Hash, StorageKey, StorageValue, Topic, Vec};
+
+// Every `Externals` backend needs either the real `native` FFI (only linked for `wasm32`) or the
+// `std`-backed `MockExternals` stand-in; there's nothing to fall back on for a `no_std` host build,
+// so fail loudly here instead of leaving callers to puzzle out an unresolved `with_externals` import.
+#[cfg(not(any(target_arch = "wasm32", feature = "std")))]
+compile_error!("ewasm_api: building for a non-wasm32 target requires the `std` feature, which provides the MockExternals backend used for off-chain testing; no_std is only supported when target_arch = \"wasm32\"");
+
+/// Abstracts over the host environment a contract runs against, mirroring the EEI surface (much
+/// like the `vm::Ext` interface host runtimes implement internally). Every public wrapper in this
+/// crate is routed through the backend resolved by [`with_externals`]: the real `native` FFI when
+/// building for `wasm32`, or [`MockExternals`] — an in-memory stand-in — everywhere else. This is
+/// what lets contract logic built on top of this crate be exercised with ordinary `cargo test`
+/// rather than only inside a real ewasm VM.
+pub trait Externals {
+    fn consume_gas(&mut self, amount: u64);
+    fn gas_left(&self) -> u64;
+
+    fn address(&self) -> Address;
+    fn balance(&self, address: &Address) -> EtherValue;
+
+    fn block_coinbase(&self) -> Address;
+    fn block_difficulty(&self) -> Difficulty;
+    fn block_gas_limit(&self) -> u64;
+    fn block_hash(&self, number: u64) -> Hash;
+    fn block_number(&self) -> u64;
+    fn block_timestamp(&self) -> u64;
+
+    fn tx_gas_price(&self) -> EtherValue;
+    fn tx_origin(&self) -> Address;
+
+    fn log(&mut self, data: &[u8], topics: &[Topic]);
+
+    fn call_mutable(
+        &mut self,
+        gas_limit: u64,
+        address: &Address,
+        value: &EtherValue,
+        data: &[u8],
+    ) -> u32;
+    fn call_code(
+        &mut self,
+        gas_limit: u64,
+        address: &Address,
+        value: &EtherValue,
+        data: &[u8],
+    ) -> u32;
+    fn call_delegate(&mut self, gas_limit: u64, address: &Address, data: &[u8]) -> u32;
+    fn call_static(&mut self, gas_limit: u64, address: &Address, data: &[u8]) -> u32;
+    fn create(&mut self, value: &EtherValue, data: &[u8]) -> (u32, Address);
+
+    fn calldata_copy(&self, from: usize, length: usize) -> Vec<u8>;
+    fn calldata_size(&self) -> usize;
+    fn caller(&self) -> Address;
+    fn callvalue(&self) -> EtherValue;
+
+    fn code_copy(&self, from: usize, length: usize) -> Vec<u8>;
+    fn code_size(&self) -> usize;
+
+    fn external_code_copy(&self, address: &Address, from: usize, length: usize) -> Vec<u8>;
+    fn external_code_size(&self, address: &Address) -> usize;
+
+    fn returndata_copy(&self, from: usize, length: usize) -> Vec<u8>;
+    fn returndata_size(&self) -> usize;
+
+    fn finish(&mut self, data: &[u8]) -> !;
+    fn revert(&mut self, data: &[u8]) -> !;
+
+    fn storage_load(&self, key: &StorageKey) -> StorageValue;
+    fn storage_store(&mut self, key: &StorageKey, value: &StorageValue);
+
+    fn selfdestruct(&mut self, address: &Address) -> !;
+}
+
+/// The real backend: every method is a thin wrapper around the corresponding `native` EEI import.
+#[cfg(target_arch = "wasm32")]
+pub struct NativeExternals;
+
+#[cfg(target_arch = "wasm32")]
+impl Externals for NativeExternals {
+    fn consume_gas(&mut self, amount: u64) {
+        unsafe { crate::native::ethereum_useGas(amount) }
+    }
+
+    fn gas_left(&self) -> u64 {
+        unsafe { crate::native::ethereum_getGasLeft() }
+    }
+
+    fn address(&self) -> Address {
+        let mut ret = Address::default();
+        unsafe { crate::native::ethereum_getAddress(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn balance(&self, address: &Address) -> EtherValue {
+        let mut ret = EtherValue::default();
+        unsafe {
+            crate::native::ethereum_getBalance(
+                address.bytes.as_ptr() as *const u32,
+                ret.bytes.as_mut_ptr() as *const u32,
+            )
+        };
+        ret
+    }
+
+    fn block_coinbase(&self) -> Address {
+        let mut ret = Address::default();
+        unsafe { crate::native::ethereum_getBlockCoinbase(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn block_difficulty(&self) -> Difficulty {
+        let mut ret = Difficulty::default();
+        unsafe { crate::native::ethereum_getBlockDifficulty(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn block_gas_limit(&self) -> u64 {
+        unsafe { crate::native::ethereum_getBlockGasLimit() }
+    }
+
+    fn block_hash(&self, number: u64) -> Hash {
+        let mut ret = Hash::default();
+        unsafe { crate::native::ethereum_getBlockHash(number, ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn block_number(&self) -> u64 {
+        unsafe { crate::native::ethereum_getBlockNumber() }
+    }
+
+    fn block_timestamp(&self) -> u64 {
+        unsafe { crate::native::ethereum_getBlockTimestamp() }
+    }
+
+    fn tx_gas_price(&self) -> EtherValue {
+        let mut ret = EtherValue::default();
+        unsafe { crate::native::ethereum_getTxGasPrice(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn tx_origin(&self) -> Address {
+        let mut ret = Address::default();
+        unsafe { crate::native::ethereum_getTxOrigin(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn log(&mut self, data: &[u8], topics: &[Topic]) {
+        let topic_ptr = |i: usize| {
+            topics
+                .get(i)
+                .map(|topic| topic.bytes.as_ptr())
+                .unwrap_or(core::ptr::null())
+        };
+
+        unsafe {
+            crate::native::ethereum_log(
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+                topics.len() as u32,
+                topic_ptr(0) as *const u32,
+                topic_ptr(1) as *const u32,
+                topic_ptr(2) as *const u32,
+                topic_ptr(3) as *const u32,
+            )
+        }
+    }
+
+    fn call_mutable(
+        &mut self,
+        gas_limit: u64,
+        address: &Address,
+        value: &EtherValue,
+        data: &[u8],
+    ) -> u32 {
+        unsafe {
+            crate::native::ethereum_call(
+                gas_limit,
+                address.bytes.as_ptr() as *const u32,
+                value.bytes.as_ptr() as *const u32,
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+            )
+        }
+    }
+
+    fn call_code(
+        &mut self,
+        gas_limit: u64,
+        address: &Address,
+        value: &EtherValue,
+        data: &[u8],
+    ) -> u32 {
+        unsafe {
+            crate::native::ethereum_callCode(
+                gas_limit,
+                address.bytes.as_ptr() as *const u32,
+                value.bytes.as_ptr() as *const u32,
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+            )
+        }
+    }
+
+    fn call_delegate(&mut self, gas_limit: u64, address: &Address, data: &[u8]) -> u32 {
+        unsafe {
+            crate::native::ethereum_callDelegate(
+                gas_limit,
+                address.bytes.as_ptr() as *const u32,
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+            )
+        }
+    }
+
+    fn call_static(&mut self, gas_limit: u64, address: &Address, data: &[u8]) -> u32 {
+        unsafe {
+            crate::native::ethereum_callStatic(
+                gas_limit,
+                address.bytes.as_ptr() as *const u32,
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+            )
+        }
+    }
+
+    fn create(&mut self, value: &EtherValue, data: &[u8]) -> (u32, Address) {
+        let mut address = Address::default();
+        let ret = unsafe {
+            crate::native::ethereum_create(
+                value.bytes.as_ptr() as *const u32,
+                data.as_ptr() as *const u32,
+                data.len() as u32,
+                address.bytes.as_mut_ptr() as *const u32,
+            )
+        };
+        (ret, address)
+    }
+
+    fn calldata_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        let mut ret = unsafe_alloc_buffer(length);
+        unsafe {
+            crate::native::ethereum_callDataCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32)
+        };
+        ret
+    }
+
+    fn calldata_size(&self) -> usize {
+        unsafe { crate::native::ethereum_getCallDataSize() as usize }
+    }
+
+    fn caller(&self) -> Address {
+        let mut ret = Address::default();
+        unsafe { crate::native::ethereum_getCaller(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn callvalue(&self) -> EtherValue {
+        let mut ret = EtherValue::default();
+        unsafe { crate::native::ethereum_getCallValue(ret.bytes.as_mut_ptr() as *const u32) };
+        ret
+    }
+
+    fn code_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        let mut ret = unsafe_alloc_buffer(length);
+        unsafe { crate::native::ethereum_codeCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32) };
+        ret
+    }
+
+    fn code_size(&self) -> usize {
+        unsafe { crate::native::ethereum_getCodeSize() as usize }
+    }
+
+    fn external_code_copy(&self, address: &Address, from: usize, length: usize) -> Vec<u8> {
+        let mut ret = unsafe_alloc_buffer(length);
+        unsafe {
+            crate::native::ethereum_externalCodeCopy(
+                address.bytes.as_ptr() as *const u32,
+                ret.as_mut_ptr() as *const u32,
+                from as u32,
+                length as u32,
+            )
+        };
+        ret
+    }
+
+    fn external_code_size(&self, address: &Address) -> usize {
+        unsafe { crate::native::ethereum_getExternalCodeSize(address.bytes.as_ptr() as *const u32) as usize }
+    }
+
+    fn returndata_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        let mut ret = unsafe_alloc_buffer(length);
+        unsafe {
+            crate::native::ethereum_returnDataCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32)
+        };
+        ret
+    }
+
+    fn returndata_size(&self) -> usize {
+        unsafe { crate::native::ethereum_getReturnDataSize() as usize }
+    }
+
+    fn finish(&mut self, data: &[u8]) -> ! {
+        unsafe { crate::native::ethereum_finish(data.as_ptr() as *const u32, data.len() as u32) }
+    }
+
+    fn revert(&mut self, data: &[u8]) -> ! {
+        unsafe { crate::native::ethereum_revert(data.as_ptr() as *const u32, data.len() as u32) }
+    }
+
+    fn storage_load(&self, key: &StorageKey) -> StorageValue {
+        let mut ret = StorageValue::default();
+        unsafe {
+            crate::native::ethereum_storageLoad(
+                key.bytes.as_ptr() as *const u32,
+                ret.bytes.as_mut_ptr() as *const u32,
+            )
+        };
+        ret
+    }
+
+    fn storage_store(&mut self, key: &StorageKey, value: &StorageValue) {
+        unsafe {
+            crate::native::ethereum_storageStore(
+                key.bytes.as_ptr() as *const u32,
+                value.bytes.as_ptr() as *const u32,
+            )
+        }
+    }
+
+    fn selfdestruct(&mut self, address: &Address) -> ! {
+        unsafe { crate::native::ethereum_selfDestruct(address.bytes.as_ptr() as *const u32) }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn with_externals<R>(f: impl FnOnce(&mut dyn Externals) -> R) -> R {
+    f(&mut NativeExternals)
+}
+
+/// Records what a contract under test did last: the data it finished or reverted with.
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+pub enum Outcome {
+    Finished(Vec<u8>),
+    Reverted(Vec<u8>),
+}
+
+/// An in-memory stand-in for the EEI, so contract logic built on top of this crate can be
+/// exercised with `cargo test` instead of only inside a real ewasm VM. Configure the fields
+/// directly (storage, balances, block context, calldata, canned call/create results) before
+/// running the code under test, then read `logs` and `outcome` back out afterwards.
+///
+/// `finish`/`revert`/`selfdestruct` can't return normally (the real EEI traps the instance), so
+/// this mock panics on each of them. `finish` and `revert` first record their data in `outcome`;
+/// drive them through `std::panic::catch_unwind` and inspect `outcome` once the unwind settles.
+/// `selfdestruct` records nothing and leaves `outcome` unchanged.
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+#[derive(Default)]
+pub struct MockExternals {
+    pub gas_left: u64,
+    pub address: Address,
+    pub caller: Address,
+    pub callvalue: EtherValue,
+    pub calldata: Vec<u8>,
+    pub code: Vec<u8>,
+    pub block_coinbase: Address,
+    pub block_difficulty: Difficulty,
+    pub block_gas_limit: u64,
+    pub block_number: u64,
+    pub block_timestamp: u64,
+    pub tx_gas_price: EtherValue,
+    pub tx_origin: Address,
+    pub block_hashes: std::collections::HashMap<u64, Hash>,
+    pub balances: std::collections::HashMap<[u8; 20], EtherValue>,
+    pub external_code: std::collections::HashMap<[u8; 20], Vec<u8>>,
+    pub storage: std::collections::HashMap<[u8; 32], StorageValue>,
+    pub logs: Vec<(Vec<u8>, Vec<Topic>)>,
+    pub returndata: Vec<u8>,
+    pub call_results: std::collections::VecDeque<(u32, Vec<u8>)>,
+    pub create_results: std::collections::VecDeque<(u32, Address)>,
+    pub outcome: Option<Outcome>,
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+impl MockExternals {
+    /// Queues the status code and return data the next `call_*` should report.
+    pub fn queue_call_result(&mut self, status: u32, returndata: Vec<u8>) {
+        self.call_results.push_back((status, returndata));
+    }
+
+    /// Queues the status code and created address the next `create` should report.
+    pub fn queue_create_result(&mut self, status: u32, address: Address) {
+        self.create_results.push_back((status, address));
+    }
+
+    fn next_call_result(&mut self) -> u32 {
+        let (status, returndata) = self
+            .call_results
+            .pop_front()
+            .expect("ewasm_api: call_mutable/call_code/call_delegate/call_static invoked against MockExternals without a queued result; call queue_call_result first");
+        self.returndata = returndata;
+        status
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+impl Externals for MockExternals {
+    fn consume_gas(&mut self, amount: u64) {
+        self.gas_left = self.gas_left.saturating_sub(amount);
+    }
+
+    fn gas_left(&self) -> u64 {
+        self.gas_left
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn balance(&self, address: &Address) -> EtherValue {
+        self.balances.get(&address.bytes).copied().unwrap_or_default()
+    }
+
+    fn block_coinbase(&self) -> Address {
+        self.block_coinbase
+    }
+
+    fn block_difficulty(&self) -> Difficulty {
+        self.block_difficulty
+    }
+
+    fn block_gas_limit(&self) -> u64 {
+        self.block_gas_limit
+    }
+
+    fn block_hash(&self, number: u64) -> Hash {
+        self.block_hashes.get(&number).copied().unwrap_or_default()
+    }
+
+    fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    fn block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
+
+    fn tx_gas_price(&self) -> EtherValue {
+        self.tx_gas_price
+    }
+
+    fn tx_origin(&self) -> Address {
+        self.tx_origin
+    }
+
+    fn log(&mut self, data: &[u8], topics: &[Topic]) {
+        self.logs.push((data.to_vec(), topics.to_vec()));
+    }
+
+    fn call_mutable(&mut self, _: u64, _: &Address, _: &EtherValue, _: &[u8]) -> u32 {
+        self.next_call_result()
+    }
+
+    fn call_code(&mut self, _: u64, _: &Address, _: &EtherValue, _: &[u8]) -> u32 {
+        self.next_call_result()
+    }
+
+    fn call_delegate(&mut self, _: u64, _: &Address, _: &[u8]) -> u32 {
+        self.next_call_result()
+    }
+
+    fn call_static(&mut self, _: u64, _: &Address, _: &[u8]) -> u32 {
+        self.next_call_result()
+    }
+
+    fn create(&mut self, _: &EtherValue, _: &[u8]) -> (u32, Address) {
+        self.create_results.pop_front().unwrap_or_default()
+    }
+
+    fn calldata_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        self.calldata[from..from + length].to_vec()
+    }
+
+    fn calldata_size(&self) -> usize {
+        self.calldata.len()
+    }
+
+    fn caller(&self) -> Address {
+        self.caller
+    }
+
+    fn callvalue(&self) -> EtherValue {
+        self.callvalue
+    }
+
+    fn code_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        self.code[from..from + length].to_vec()
+    }
+
+    fn code_size(&self) -> usize {
+        self.code.len()
+    }
+
+    fn external_code_copy(&self, address: &Address, from: usize, length: usize) -> Vec<u8> {
+        self.external_code
+            .get(&address.bytes)
+            .map(|code| code[from..from + length].to_vec())
+            .unwrap_or_default()
+    }
+
+    fn external_code_size(&self, address: &Address) -> usize {
+        self.external_code.get(&address.bytes).map_or(0, Vec::len)
+    }
+
+    fn returndata_copy(&self, from: usize, length: usize) -> Vec<u8> {
+        self.returndata[from..from + length].to_vec()
+    }
+
+    fn returndata_size(&self) -> usize {
+        self.returndata.len()
+    }
+
+    fn finish(&mut self, data: &[u8]) -> ! {
+        self.outcome = Some(Outcome::Finished(data.to_vec()));
+        panic!("ewasm_api: finish() called against MockExternals; catch with std::panic::catch_unwind and inspect MockExternals::outcome")
+    }
+
+    fn revert(&mut self, data: &[u8]) -> ! {
+        self.outcome = Some(Outcome::Reverted(data.to_vec()));
+        panic!("ewasm_api: revert() called against MockExternals; catch with std::panic::catch_unwind and inspect MockExternals::outcome")
+    }
+
+    fn storage_load(&self, key: &StorageKey) -> StorageValue {
+        self.storage.get(&key.bytes).copied().unwrap_or_default()
+    }
+
+    fn storage_store(&mut self, key: &StorageKey, value: &StorageValue) {
+        self.storage.insert(key.bytes, *value);
+    }
+
+    fn selfdestruct(&mut self, _address: &Address) -> ! {
+        panic!("ewasm_api: selfdestruct() called against MockExternals")
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+std::thread_local! {
+    static MOCK: std::cell::RefCell<MockExternals> = std::cell::RefCell::new(MockExternals::default());
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+pub fn with_externals<R>(f: impl FnOnce(&mut dyn Externals) -> R) -> R {
+    MOCK.with(|mock| f(&mut *mock.borrow_mut()))
+}
+
+/// Configures or inspects the thread-local `MockExternals` backend used by non-`wasm32` builds.
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+pub fn mock<R>(f: impl FnOnce(&mut MockExternals) -> R) -> R {
+    MOCK.with(|mock| f(&mut mock.borrow_mut()))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32"), feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_call_result_feeds_call_mutable_status() {
+        mock(|m| m.queue_call_result(0, b"hello".to_vec()));
+
+        let status = with_externals(|ext| {
+            ext.call_mutable(0, &Address::default(), &EtherValue::default(), &[])
+        });
+        assert_eq!(status, 0);
+
+        let returndata = with_externals(|ext| ext.returndata_copy(0, 5));
+        assert_eq!(returndata, b"hello");
+    }
+
+    #[test]
+    #[should_panic(expected = "without a queued result")]
+    fn call_mutable_without_a_queued_result_panics() {
+        with_externals(|ext| {
+            ext.call_mutable(0, &Address::default(), &EtherValue::default(), &[]);
+        });
+    }
+
+    #[test]
+    fn storage_load_store_round_trip() {
+        let key = StorageKey::default();
+        let mut value = StorageValue::default();
+        value.bytes[0] = 42;
+
+        with_externals(|ext| ext.storage_store(&key, &value));
+        let loaded = with_externals(|ext| ext.storage_load(&key));
+        assert_eq!(loaded.bytes, value.bytes);
+    }
+
+    #[test]
+    fn finish_data_records_outcome() {
+        let unwound = std::panic::catch_unwind(|| with_externals(|ext| ext.finish(b"done")));
+        assert!(unwound.is_err());
+
+        mock(|m| match &m.outcome {
+            Some(Outcome::Finished(data)) => assert_eq!(data, b"done"),
+            _ => panic!("expected a recorded Finished outcome"),
+        });
+    }
+
+    #[test]
+    fn revert_data_records_outcome() {
+        let unwound = std::panic::catch_unwind(|| with_externals(|ext| ext.revert(b"nope")));
+        assert!(unwound.is_err());
+
+        mock(|m| match &m.outcome {
+            Some(Outcome::Reverted(data)) => assert_eq!(data, b"nope"),
+            _ => panic!("expected a recorded Reverted outcome"),
+        });
+    }
+}
@@ -0,0 +1,118 @@
+use super::{Bytes20, Bytes32, Uint128, Uint256};
+use ethereum_types::{H160, H256, U128, U256};
+
+// `Uint128`/`Uint256` hold their bytes little-endian, as returned by the EEI (`external_balance`,
+// `tx_gas_price`, `block_difficulty`, `callvalue`, ...). `ethereum_types`' `U128`/`U256` are
+// big-endian internally but expose little-endian byte conversions directly, so no manual
+// byte-reversal is needed here.
+
+impl From<Uint128> for U128 {
+    fn from(value: Uint128) -> U128 {
+        U128::from_little_endian(&value.bytes)
+    }
+}
+
+impl From<U128> for Uint128 {
+    fn from(value: U128) -> Uint128 {
+        let mut ret = Uint128::default();
+        value.to_little_endian(&mut ret.bytes);
+        ret
+    }
+}
+
+impl From<Uint256> for U256 {
+    fn from(value: Uint256) -> U256 {
+        U256::from_little_endian(&value.bytes)
+    }
+}
+
+impl From<U256> for Uint256 {
+    fn from(value: U256) -> Uint256 {
+        let mut ret = Uint256::default();
+        value.to_little_endian(&mut ret.bytes);
+        ret
+    }
+}
+
+// `Bytes20`/`Bytes32` are opaque identifiers (addresses, hashes, storage keys/values) rather than
+// integers, so their byte order already matches `H160`/`H256` and no reversal is needed.
+
+impl From<Bytes20> for H160 {
+    fn from(value: Bytes20) -> H160 {
+        H160(value.bytes)
+    }
+}
+
+impl From<H160> for Bytes20 {
+    fn from(value: H160) -> Bytes20 {
+        Bytes20 { bytes: value.0 }
+    }
+}
+
+impl From<Bytes32> for H256 {
+    fn from(value: Bytes32) -> H256 {
+        H256(value.bytes)
+    }
+}
+
+impl From<H256> for Bytes32 {
+    fn from(value: H256) -> Bytes32 {
+        Bytes32 { bytes: value.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uint128_round_trips_through_u128_little_endian() {
+        let mut native = Uint128::default();
+        native.bytes[0] = 0x2a; // low byte -> value 298 in little-endian
+        native.bytes[1] = 0x01;
+
+        let converted: U128 = native.into();
+        assert_eq!(converted, U128::from(298));
+
+        let back: Uint128 = converted.into();
+        assert_eq!(back.bytes, native.bytes);
+    }
+
+    #[test]
+    fn uint256_round_trips_through_u256_little_endian() {
+        let mut native = Uint256::default();
+        native.bytes[0] = 0xff;
+
+        let converted: U256 = native.into();
+        assert_eq!(converted, U256::from(0xff));
+
+        let back: Uint256 = converted.into();
+        assert_eq!(back.bytes, native.bytes);
+    }
+
+    #[test]
+    fn bytes20_round_trips_through_h160_without_byte_reversal() {
+        let mut native = Bytes20::default();
+        native.bytes[0] = 0x11;
+        native.bytes[19] = 0x22;
+
+        let converted: H160 = native.into();
+        assert_eq!(converted.0, native.bytes);
+
+        let back: Bytes20 = converted.into();
+        assert_eq!(back.bytes, native.bytes);
+    }
+
+    #[test]
+    fn bytes32_round_trips_through_h256_without_byte_reversal() {
+        let mut native = Bytes32::default();
+        native.bytes[0] = 0x33;
+        native.bytes[31] = 0x44;
+
+        let converted: H256 = native.into();
+        assert_eq!(converted.0, native.bytes);
+
+        let back: Bytes32 = converted.into();
+        assert_eq!(back.bytes, native.bytes);
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 /// ewasm_api is a library used to interface with Ethereum's EEI in Ewasm, a set of enhancements to
 /// the Ethereum smart contract platform.
 /// ewasm_api exposes both a set of unsafe "native" functions representing the actual EEI
@@ -6,22 +7,47 @@
 ///
 /// To use ewasm_api, simply include it as a dependency in your project.
 ///
+/// By default, ewasm_api links against `std`. Real ewasm contracts are freestanding wasm and
+/// cannot link std, so the `std` feature can be disabled (it is on by default) to build against
+/// `core`/`alloc` instead with `#![no_std]`.
+///
 /// # Examples
-/// ```
+/// ```ignore
 /// extern crate ewasm_api;
 ///
 /// use ewasm_api::{block_hash, finish_data};
 ///
 /// #[no_mangle]
 /// pub extern "C" fn main() {
-///     let a: Hash = block_hash(1);
+///     let a = block_hash(1);
 ///     finish_data(&a.bytes);
 /// }
 /// ```
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use std::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+/// Conversions between the native `Uint128`/`Uint256`/`Bytes20`/`Bytes32` types and the
+/// `ethereum_types` crate used throughout the EVM/wasm hosts. Gated behind the `ethereum-types`
+/// feature so contracts that don't need it aren't forced to depend on it.
+#[cfg(feature = "ethereum-types")]
+mod interop;
+
+/// The pluggable host backend: the `Externals` trait, the real `native`-backed implementation, and
+/// the `MockExternals` stand-in used to unit-test contracts off-chain on non-`wasm32` targets.
+mod externals;
+pub use externals::Externals;
+#[cfg(all(not(target_arch = "wasm32"), feature = "std"))]
+pub use externals::{mock, MockExternals, Outcome};
+use externals::with_externals;
 
 /// The native host interface exposed to the ewasm contract. Do not use these functions unless, for
-/// some reason, the safe wrapper is not flexible enough.
+/// some reason, the safe wrapper is not flexible enough. Only linked when actually targeting
+/// `wasm32`; non-`wasm32` builds with `std` run against [`externals::MockExternals`] instead.
+#[cfg(target_arch = "wasm32")]
 mod native {
     extern "C" {
         pub fn ethereum_useGas(amount: u64);
@@ -100,6 +126,7 @@ mod native {
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 fn unsafe_alloc_buffer(len: usize) -> Vec<u8> {
     let mut ret: Vec<u8> = Vec::with_capacity(len);
     unsafe {
@@ -132,6 +159,52 @@ pub struct Bytes32 {
     pub bytes: [u8; 32],
 }
 
+/// Implements `is_zero`, byte-wise equality, and byte-wise ordering for a fixed-size byte array
+/// newtype. The ordering compares from the most significant byte down, so it gives correct
+/// numeric ordering for the little-endian integer types and a stable, if not numerically
+/// meaningful, ordering for the opaque identifier types.
+macro_rules! impl_bytes_eq_and_ord {
+    ($ty:ident) => {
+        impl $ty {
+            /// Returns whether every byte of this value is zero.
+            pub fn is_zero(&self) -> bool {
+                self.bytes.iter().all(|&b| b == 0)
+            }
+        }
+
+        impl PartialEq for $ty {
+            fn eq(&self, other: &$ty) -> bool {
+                self.bytes == other.bytes
+            }
+        }
+
+        impl Eq for $ty {}
+
+        impl PartialOrd for $ty {
+            fn partial_cmp(&self, other: &$ty) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $ty {
+            fn cmp(&self, other: &$ty) -> core::cmp::Ordering {
+                for i in (0..self.bytes.len()).rev() {
+                    match self.bytes[i].cmp(&other.bytes[i]) {
+                        core::cmp::Ordering::Equal => continue,
+                        ord => return ord,
+                    }
+                }
+                core::cmp::Ordering::Equal
+            }
+        }
+    };
+}
+
+impl_bytes_eq_and_ord!(Uint128);
+impl_bytes_eq_and_ord!(Uint256);
+impl_bytes_eq_and_ord!(Bytes20);
+impl_bytes_eq_and_ord!(Bytes32);
+
 type EtherValue = Uint128;
 type Address = Bytes20;
 type StorageKey = Bytes32;
@@ -140,10 +213,19 @@ type Topic = Bytes32;
 type Hash = Bytes32;
 type Difficulty = Uint256;
 
-/// Enum representing an error code for EEI calls. Currently used by `codeCopy`, `callDataCopy`,
-/// `externalCodeCopy`, and `returnDataCopy`.
+/// Enum representing an error arising from an EEI call. `OutOfBoundsCopy` is used by `codeCopy`,
+/// `callDataCopy`, `externalCodeCopy`, and `returnDataCopy`. `UnknownCallStatus` is used by
+/// `call`, `callCode`, `callDelegate`, `callStatic`, and `create` when the host returns a status
+/// code outside the ones currently specified by the EEI, so a malformed or future host response
+/// can be reverted on instead of trapping the whole contract. `CallFailed` and `CallReverted` are
+/// used by the `_with_return` call helpers, which fold a `CallResult` and its return data into a
+/// single `Result`.
+#[derive(Debug, PartialEq)]
 pub enum Error {
     OutOfBoundsCopy,
+    UnknownCallStatus(u32),
+    CallFailed,
+    CallReverted(Vec<u8>),
 }
 
 /// Enum describing the result of a call. Used by `call`, `callCode`, `callDelegate`, and
@@ -165,191 +247,87 @@ pub enum CreateResult {
 /// Subtracts the given amount from the VM's gas counter. This is usually injected by the metering
 /// contract at deployment time, and hence is unneeded in most cases.
 pub fn consume_gas(amount: u64) {
-    unsafe {
-        native::ethereum_useGas(amount);
-    }
+    with_externals(|ext| ext.consume_gas(amount))
 }
 
 /// Returns the gas left in the current call.
 pub fn gas_left() -> u64 {
-    unsafe { native::ethereum_getGasLeft() }
+    with_externals(|ext| ext.gas_left())
 }
 
 /// Returns the executing address.
 pub fn current_address() -> Address {
-    let mut ret = Address::default();
-
-    unsafe {
-        native::ethereum_getAddress(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.address())
 }
 
 /// Returns the balance of the address given.
 pub fn external_balance(address: &Address) -> EtherValue {
-    let mut ret = EtherValue::default();
-
-    unsafe {
-        native::ethereum_getBalance(
-            address.bytes.as_ptr() as *const u32,
-            ret.bytes.as_mut_ptr() as *const u32,
-        );
-    }
-
-    ret
+    with_externals(|ext| ext.balance(address))
 }
 
 /// Returns the beneficiary address for the block this transaction is in (current block)
 pub fn block_coinbase() -> Address {
-    let mut ret = Address::default();
-
-    unsafe {
-        native::ethereum_getBlockCoinbase(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.block_coinbase())
 }
 
 /// Returns the difficulty of the most recent block.
 pub fn block_difficulty() -> Difficulty {
-    let mut ret = Difficulty::default();
-
-    unsafe {
-        native::ethereum_getBlockDifficulty(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.block_difficulty())
 }
 
 /// Returns the gas limit of the most recent block.
 pub fn block_gas_limit() -> u64 {
-    unsafe { native::ethereum_getBlockGasLimit() }
+    with_externals(|ext| ext.block_gas_limit())
 }
 
 /// Returns the hash of the `number`th most recent block.
 pub fn block_hash(number: u64) -> Hash {
-    let mut ret = Hash::default();
-
-    unsafe {
-        native::ethereum_getBlockHash(number, ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.block_hash(number))
 }
 
 /// Returns the number of the most recent block.
 pub fn block_number() -> u64 {
-    unsafe { native::ethereum_getBlockNumber() }
+    with_externals(|ext| ext.block_number())
 }
 
 /// Returns the timestamp of the most recent block.
 pub fn block_timestamp() -> u64 {
-    unsafe { native::ethereum_getBlockTimestamp() }
+    with_externals(|ext| ext.block_timestamp())
 }
 
 /// Returns the gas price of the currently executing call.
 pub fn tx_gas_price() -> EtherValue {
-    let mut ret = EtherValue::default();
-
-    unsafe {
-        native::ethereum_getTxGasPrice(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.tx_gas_price())
 }
 
 /// Returns the address of the original transaction sender.
 pub fn tx_origin() -> Address {
-    let mut ret = Address::default();
-
-    unsafe {
-        native::ethereum_getTxOrigin(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
-}
-
-/// Appends log data to the transaction receipt, with a variable number of topics.
-fn log(
-    data: &[u8],
-    topic_count: usize,
-    topic1: *const u8,
-    topic2: *const u8,
-    topic3: *const u8,
-    topic4: *const u8,
-) {
-    unsafe {
-        native::ethereum_log(
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-            topic_count as u32,
-            topic1 as *const u32,
-            topic2 as *const u32,
-            topic3 as *const u32,
-            topic4 as *const u32,
-        );
-    }
+    with_externals(|ext| ext.tx_origin())
 }
 
 /// Appends log data without a topic.
 pub fn log0(data: &[u8]) {
-    log(
-        data,
-        0,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-    )
+    with_externals(|ext| ext.log(data, &[]))
 }
 
 /// Appends log data with one topic.
 pub fn log1(data: &[u8], topic1: &Topic) {
-    log(
-        data,
-        1,
-        topic1.bytes.as_ptr() as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-    )
+    with_externals(|ext| ext.log(data, &[*topic1]))
 }
 
 /// Appends log data with two topics.
 pub fn log2(data: &[u8], topic1: &Topic, topic2: &Topic) {
-    log(
-        data,
-        2,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        0 as *const u8,
-        0 as *const u8,
-    )
+    with_externals(|ext| ext.log(data, &[*topic1, *topic2]))
 }
 
 /// Appends log data with three topics.
 pub fn log3(data: &[u8], topic1: &Topic, topic2: &Topic, topic3: &Topic) {
-    log(
-        data,
-        3,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        topic3.bytes.as_ptr() as *const u8,
-        0 as *const u8,
-    )
+    with_externals(|ext| ext.log(data, &[*topic1, *topic2, *topic3]))
 }
 
 /// Appends log data with four topics.
 pub fn log4(data: &[u8], topic1: &Topic, topic2: &Topic, topic3: &Topic, topic4: &Topic) {
-    log(
-        data,
-        4,
-        topic1.bytes.as_ptr() as *const u8,
-        topic2.bytes.as_ptr() as *const u8,
-        topic3.bytes.as_ptr() as *const u8,
-        topic4.bytes.as_ptr() as *const u8,
-    )
+    with_externals(|ext| ext.log(data, &[*topic1, *topic2, *topic3, *topic4]))
 }
 
 /// Executes a standard call to the specified address with the given gas limit, ether value, and
@@ -359,114 +337,117 @@ pub fn call_mutable(
     address: &Address,
     value: &EtherValue,
     data: &[u8],
-) -> CallResult {
-    let ret = unsafe {
-        native::ethereum_call(
-            gas_limit,
-            address.bytes.as_ptr() as *const u32,
-            value.bytes.as_ptr() as *const u32,
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-        )
-    };
-
-    match ret {
-        0 => CallResult::Successful,
-        1 => CallResult::Failure,
-        2 => CallResult::Revert,
-        _ => panic!(),
+) -> Result<CallResult, Error> {
+    match with_externals(|ext| ext.call_mutable(gas_limit, address, value, data)) {
+        0 => Ok(CallResult::Successful),
+        1 => Ok(CallResult::Failure),
+        2 => Ok(CallResult::Revert),
+        ret => Err(Error::UnknownCallStatus(ret)),
     }
 }
 
 /// Executes another account's code in the context of the caller.
-pub fn call_code(gas_limit: u64, address: &Address, value: &EtherValue, data: &[u8]) -> CallResult {
-    let ret = unsafe {
-        native::ethereum_callCode(
-            gas_limit,
-            address.bytes.as_ptr() as *const u32,
-            value.bytes.as_ptr() as *const u32,
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-        )
-    };
-
-    match ret {
-        0 => CallResult::Successful,
-        1 => CallResult::Failure,
-        2 => CallResult::Revert,
-        _ => panic!(),
+pub fn call_code(
+    gas_limit: u64,
+    address: &Address,
+    value: &EtherValue,
+    data: &[u8],
+) -> Result<CallResult, Error> {
+    match with_externals(|ext| ext.call_code(gas_limit, address, value, data)) {
+        0 => Ok(CallResult::Successful),
+        1 => Ok(CallResult::Failure),
+        2 => Ok(CallResult::Revert),
+        ret => Err(Error::UnknownCallStatus(ret)),
     }
 }
 
 /// Executes a call similar to `call_code`, but retaining the currently executing call's sender
 /// and value.
-pub fn call_delegate(gas_limit: u64, address: &Address, data: &[u8]) -> CallResult {
-    let ret = unsafe {
-        native::ethereum_callDelegate(
-            gas_limit,
-            address.bytes.as_ptr() as *const u32,
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-        )
-    };
-
-    match ret {
-        0 => CallResult::Successful,
-        1 => CallResult::Failure,
-        2 => CallResult::Revert,
-        _ => panic!(),
+pub fn call_delegate(gas_limit: u64, address: &Address, data: &[u8]) -> Result<CallResult, Error> {
+    match with_externals(|ext| ext.call_delegate(gas_limit, address, data)) {
+        0 => Ok(CallResult::Successful),
+        1 => Ok(CallResult::Failure),
+        2 => Ok(CallResult::Revert),
+        ret => Err(Error::UnknownCallStatus(ret)),
     }
 }
 
 /// Executes a static call which cannot mutate the state.
-pub fn call_static(gas_limit: u64, address: &Address, data: &[u8]) -> CallResult {
-    let ret = unsafe {
-        native::ethereum_callStatic(
-            gas_limit,
-            address.bytes.as_ptr() as *const u32,
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-        )
-    };
-
-    match ret {
-        0 => CallResult::Successful,
-        1 => CallResult::Failure,
-        2 => CallResult::Revert,
-        _ => panic!(),
+pub fn call_static(gas_limit: u64, address: &Address, data: &[u8]) -> Result<CallResult, Error> {
+    match with_externals(|ext| ext.call_static(gas_limit, address, data)) {
+        0 => Ok(CallResult::Successful),
+        1 => Ok(CallResult::Failure),
+        2 => Ok(CallResult::Revert),
+        ret => Err(Error::UnknownCallStatus(ret)),
     }
 }
 
 /// Creates a contract with the the given code, sending the specified ether value to its address.
-pub fn create(value: &EtherValue, data: &[u8]) -> CreateResult {
-    let mut address = Address::default();
-
-    let ret = unsafe {
-        native::ethereum_create(
-            value.bytes.as_ptr() as *const u32,
-            data.as_ptr() as *const u32,
-            data.len() as u32,
-            address.bytes.as_mut_ptr() as *const u32,
-        )
-    };
+pub fn create(value: &EtherValue, data: &[u8]) -> Result<CreateResult, Error> {
+    let (ret, address) = with_externals(|ext| ext.create(value, data));
 
     match ret {
-        0 => CreateResult::Successful(address),
-        1 => CreateResult::Failure,
-        2 => CreateResult::Revert,
-        _ => panic!(),
+        0 => Ok(CreateResult::Successful(address)),
+        1 => Ok(CreateResult::Failure),
+        2 => Ok(CreateResult::Revert),
+        _ => Err(Error::UnknownCallStatus(ret)),
     }
 }
 
-/// Executes callDataCopy, but does not check for overflow.
-pub fn unsafe_calldata_copy(from: usize, length: usize) -> Vec<u8> {
-    let mut ret: Vec<u8> = unsafe_alloc_buffer(length);
-
-    unsafe {
-        native::ethereum_callDataCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32);
+/// Folds a `CallResult` into the callee's return data: `Successful` yields its output,
+/// `Failure` yields `Error::CallFailed`, and `Revert` yields `Error::CallReverted` carrying the
+/// revert payload.
+fn call_result_into_return(result: CallResult) -> Result<Vec<u8>, Error> {
+    match result {
+        CallResult::Successful => Ok(returndata_acquire().to_vec()),
+        CallResult::Failure => Err(Error::CallFailed),
+        CallResult::Revert => Err(Error::CallReverted(returndata_acquire().to_vec())),
     }
+}
 
-    ret
+/// Executes a standard call like `call_mutable`, then captures and returns the callee's output in
+/// one step instead of requiring a separate `returndata_acquire`.
+pub fn call_mutable_with_return(
+    gas_limit: u64,
+    address: &Address,
+    value: &EtherValue,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    call_mutable(gas_limit, address, value, data).and_then(call_result_into_return)
+}
+
+/// Executes a call like `call_code`, then captures and returns the callee's output in one step.
+pub fn call_code_with_return(
+    gas_limit: u64,
+    address: &Address,
+    value: &EtherValue,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    call_code(gas_limit, address, value, data).and_then(call_result_into_return)
+}
+
+/// Executes a call like `call_delegate`, then captures and returns the callee's output in one
+/// step.
+pub fn call_delegate_with_return(
+    gas_limit: u64,
+    address: &Address,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    call_delegate(gas_limit, address, data).and_then(call_result_into_return)
+}
+
+/// Executes a call like `call_static`, then captures and returns the callee's output in one step.
+pub fn call_static_with_return(
+    gas_limit: u64,
+    address: &Address,
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    call_static(gas_limit, address, data).and_then(call_result_into_return)
+}
+
+/// Executes callDataCopy, but does not check for overflow.
+pub fn unsafe_calldata_copy(from: usize, length: usize) -> Vec<u8> {
+    with_externals(|ext| ext.calldata_copy(from, length))
 }
 
 /// Returns a vector containing all data passed with the currently executing call.
@@ -487,40 +468,22 @@ pub fn calldata_copy(from: usize, length: usize) -> Result<Vec<u8>, Error> {
 
 /// Returns the length of the call data supplied with the currently executing call.
 pub fn calldata_size() -> usize {
-    unsafe { native::ethereum_getCallDataSize() as usize }
+    with_externals(|ext| ext.calldata_size())
 }
 
 /// Returns the sender of the currently executing call.
 pub fn caller() -> Address {
-    let mut ret = Address::default();
-
-    unsafe {
-        native::ethereum_getCaller(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.caller())
 }
 
 /// Returns the value sent with the currently executing call.
 pub fn callvalue() -> EtherValue {
-    let mut ret = EtherValue::default();
-
-    unsafe {
-        native::ethereum_getCallValue(ret.bytes.as_mut_ptr() as *const u32);
-    }
-
-    ret
+    with_externals(|ext| ext.callvalue())
 }
 
 /// Executes codeCopy, but does not check for overflow.
 pub fn unsafe_code_copy(from: usize, length: usize) -> Vec<u8> {
-    let mut ret: Vec<u8> = unsafe_alloc_buffer(length);
-
-    unsafe {
-        native::ethereum_codeCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32);
-    }
-
-    ret
+    with_externals(|ext| ext.code_copy(from, length))
 }
 
 /// Returns the currently executing code.
@@ -541,23 +504,12 @@ pub fn code_copy(from: usize, length: usize) -> Result<Vec<u8>, Error> {
 
 /// Returns the size of the currently executing code.
 pub fn code_size() -> usize {
-    unsafe { native::ethereum_getCodeSize() as usize }
+    with_externals(|ext| ext.code_size())
 }
 
 /// Executes externalCodeCopy, but does not check for overflow.
 pub fn unsafe_external_code_copy(address: &Address, from: usize, length: usize) -> Vec<u8> {
-    let mut ret: Vec<u8> = unsafe_alloc_buffer(length);
-
-    unsafe {
-        native::ethereum_externalCodeCopy(
-            address.bytes.as_ptr() as *const u32,
-            ret.as_mut_ptr() as *const u32,
-            from as u32,
-            length as u32,
-        );
-    }
-
-    ret
+    with_externals(|ext| ext.external_code_copy(address, from, length))
 }
 
 /// Returns the code at the specified address.
@@ -578,23 +530,50 @@ pub fn external_code_copy(address: &Address, from: usize, length: usize) -> Resu
 
 /// Returns the size of the code at the specified address.
 pub fn external_code_size(address: &Address) -> usize {
-    unsafe { native::ethereum_getExternalCodeSize(address.bytes.as_ptr() as *const u32) as usize }
+    with_externals(|ext| ext.external_code_size(address))
 }
 
-/// Executes returnDataCopy, but does not check for overflow.
-pub fn unsafe_returndata_copy(from: usize, length: usize) -> Vec<u8> {
-    let mut ret: Vec<u8> = unsafe_alloc_buffer(length);
+/// Owns a single copy of the VM's return buffer, letting callers take sub-slices of the result
+/// without re-entering the host or re-allocating.
+pub struct ReturnData {
+    mem: Vec<u8>,
+    offset: usize,
+    size: usize,
+}
 
-    unsafe {
-        native::ethereum_returnDataCopy(ret.as_mut_ptr() as *const u32, from as u32, length as u32);
+impl ReturnData {
+    /// Returns an empty `ReturnData`, for use when no call has produced return data yet.
+    pub fn empty() -> ReturnData {
+        ReturnData {
+            mem: Vec::new(),
+            offset: 0,
+            size: 0,
+        }
     }
+}
 
-    ret
+impl core::ops::Deref for ReturnData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.mem[self.offset..self.offset + self.size]
+    }
 }
 
-/// Returns the data in the VM's return buffer.
-pub fn returndata_acquire() -> Vec<u8> {
-    unsafe_returndata_copy(0, returndata_size())
+/// Executes returnDataCopy, but does not check for overflow.
+pub fn unsafe_returndata_copy(from: usize, length: usize) -> Vec<u8> {
+    with_externals(|ext| ext.returndata_copy(from, length))
+}
+
+/// Copies the data in the VM's return buffer into a `ReturnData`.
+pub fn returndata_acquire() -> ReturnData {
+    let size = returndata_size();
+
+    ReturnData {
+        mem: unsafe_returndata_copy(0, size),
+        offset: 0,
+        size,
+    }
 }
 
 /// Returns the segment of return buffer data beginning at `from` and continuing for `length` bytes.
@@ -610,64 +589,105 @@ pub fn returndata_copy(from: usize, length: usize) -> Result<Vec<u8>, Error> {
 
 /// Returns the length of the data in the VM's return buffer.
 pub fn returndata_size() -> usize {
-    unsafe { native::ethereum_getReturnDataSize() as usize }
+    with_externals(|ext| ext.returndata_size())
 }
 
 /// Halts execution and reverts all changes to the state.
 pub fn revert() -> ! {
-    unsafe {
-        native::ethereum_revert(0 as *const u32, 0 as u32);
-    }
+    with_externals(|ext| ext.revert(&[]))
 }
 
 /// Fills the return buffer with the given data and halts execution, reverting all state changes.
 pub fn revert_data(data: &[u8]) -> ! {
-    unsafe {
-        native::ethereum_revert(data.as_ptr() as *const u32, data.len() as u32);
-    }
+    with_externals(|ext| ext.revert(data))
 }
 
 /// Ends execution, signalling success.
 pub fn finish() -> ! {
-    unsafe {
-        native::ethereum_finish(0 as *const u32, 0 as u32);
-    }
+    with_externals(|ext| ext.finish(&[]))
 }
 
 /// Fills the return buffer with the given data and halts execution, signalling success.
 pub fn finish_data(data: &[u8]) -> ! {
-    unsafe {
-        native::ethereum_finish(data.as_ptr() as *const u32, data.len() as u32);
-    }
+    with_externals(|ext| ext.finish(data))
 }
 
 /// Accesses the storage data at the specified key.
 pub fn storage_load(key: &StorageKey) -> StorageValue {
-    let mut ret = StorageValue::default();
-
-    unsafe {
-        native::ethereum_storageLoad(
-            key.bytes.as_ptr() as *const u32,
-            ret.bytes.as_mut_ptr() as *const u32,
-        );
-    }
-
-    ret
+    with_externals(|ext| ext.storage_load(key))
 }
 
 /// Sets the storage data at the specified key.
 pub fn storage_store(key: &StorageKey, value: &StorageValue) {
-    unsafe {
-        native::ethereum_storageStore(
-            key.bytes.as_ptr() as *const u32,
-            value.bytes.as_ptr() as *const u32,
-        );
-    }
+    with_externals(|ext| ext.storage_store(key, value))
 }
 
 /// Self-destructs the running contract, sending all its ether to a specified beneficiary address.
 pub fn selfdestruct(address: &Address) -> ! {
-    unsafe {
-        native::ethereum_selfDestruct(address.bytes.as_ptr() as *const u32);
+    with_externals(|ext| ext.selfdestruct(address))
+}
+
+#[cfg(all(test, not(target_arch = "wasm32"), feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn call_mutable_with_return_yields_callee_output_on_success() {
+        mock(|m| m.queue_call_result(0, b"result".to_vec()));
+
+        let ret =
+            call_mutable_with_return(0, &Address::default(), &EtherValue::default(), &[]).unwrap();
+        assert_eq!(ret, b"result");
+    }
+
+    #[test]
+    fn call_mutable_with_return_yields_call_failed_on_failure() {
+        mock(|m| m.queue_call_result(1, Vec::new()));
+
+        let err =
+            call_mutable_with_return(0, &Address::default(), &EtherValue::default(), &[]).unwrap_err();
+        assert_eq!(err, Error::CallFailed);
+    }
+
+    #[test]
+    fn call_mutable_with_return_yields_call_reverted_with_payload_on_revert() {
+        mock(|m| m.queue_call_result(2, b"why".to_vec()));
+
+        let err =
+            call_mutable_with_return(0, &Address::default(), &EtherValue::default(), &[]).unwrap_err();
+        assert_eq!(err, Error::CallReverted(b"why".to_vec()));
+    }
+
+    #[test]
+    fn call_code_with_return_yields_callee_output_on_success() {
+        mock(|m| m.queue_call_result(0, b"code".to_vec()));
+
+        let ret =
+            call_code_with_return(0, &Address::default(), &EtherValue::default(), &[]).unwrap();
+        assert_eq!(ret, b"code");
+    }
+
+    #[test]
+    fn call_delegate_with_return_yields_callee_output_on_success() {
+        mock(|m| m.queue_call_result(0, b"delegate".to_vec()));
+
+        let ret = call_delegate_with_return(0, &Address::default(), &[]).unwrap();
+        assert_eq!(ret, b"delegate");
+    }
+
+    #[test]
+    fn call_static_with_return_yields_callee_output_on_success() {
+        mock(|m| m.queue_call_result(0, b"static".to_vec()));
+
+        let ret = call_static_with_return(0, &Address::default(), &[]).unwrap();
+        assert_eq!(ret, b"static");
+    }
+
+    #[test]
+    fn call_static_with_return_yields_unknown_call_status_on_unrecognized_code() {
+        mock(|m| m.queue_call_result(99, Vec::new()));
+
+        let err = call_static_with_return(0, &Address::default(), &[]).unwrap_err();
+        assert_eq!(err, Error::UnknownCallStatus(99));
     }
 }